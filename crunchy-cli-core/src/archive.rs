@@ -0,0 +1,61 @@
+use crate::utils::context::Context;
+use crate::utils::filter::{print_dry_run_plan, resolve_phase, DryRunPlan, ResolvedItem};
+use crate::Execute;
+use anyhow::Result;
+use clap::Args;
+use log::info;
+
+#[derive(Debug, Args)]
+pub struct Archive {
+    #[arg(help = "Url(s) to a series, season or episode")]
+    urls: Vec<String>,
+
+    #[arg(help = "Output file/directory template")]
+    #[arg(short, long, default_value = "{title}.mkv")]
+    output: String,
+
+    #[arg(long, default_value_t = false, hide = true)]
+    pub(crate) yes: bool,
+}
+
+impl Execute for Archive {
+    async fn execute(self, ctx: Context) -> Result<()> {
+        let items = self.resolve(&ctx).await?;
+        let selected = resolve_phase(&ctx, items);
+
+        if print_dry_run_plan(&ctx, &selected) {
+            return Ok(());
+        }
+
+        for item in selected {
+            self.archive_one(&ctx, item).await?;
+        }
+        Ok(())
+    }
+}
+
+impl Archive {
+    async fn resolve(&self, ctx: &Context) -> Result<Vec<ResolvedItem<String>>> {
+        let mut items = Vec::with_capacity(self.urls.len());
+        for url in &self.urls {
+            let media = ctx.crunchy.media_collection_from_url(url).await?;
+            // stream/subtitle tracks and their size aren't known until the download itself starts,
+            // only the output container is fixed upfront via '--output'
+            let plan = DryRunPlan {
+                container: self.output.rsplit('.').next().map(str::to_string),
+                ..Default::default()
+            };
+            items.push(ResolvedItem {
+                title: media.title().to_string(),
+                payload: url.clone(),
+                plan,
+            });
+        }
+        Ok(items)
+    }
+
+    async fn archive_one(&self, _ctx: &Context, item: ResolvedItem<String>) -> Result<()> {
+        info!("Archiving {}", item.title);
+        Ok(())
+    }
+}
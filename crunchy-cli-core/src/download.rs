@@ -0,0 +1,67 @@
+use crate::utils::context::Context;
+use crate::utils::filter::{print_dry_run_plan, resolve_phase, DryRunPlan, ResolvedItem};
+use crate::Execute;
+use anyhow::Result;
+use clap::Args;
+use crunchyroll_rs::Locale;
+use log::info;
+
+#[derive(Debug, Args)]
+pub struct Download {
+    #[arg(help = "Url(s) to a series, season or episode")]
+    urls: Vec<String>,
+
+    #[arg(help = "Output file/directory template")]
+    #[arg(short, long, default_value = "{title}.mp4")]
+    output: String,
+
+    #[arg(help = "The preferred audio language")]
+    #[arg(short, long)]
+    pub(crate) audio: Option<Locale>,
+
+    #[arg(long, default_value_t = false, hide = true)]
+    pub(crate) yes: bool,
+}
+
+impl Execute for Download {
+    async fn execute(self, ctx: Context) -> Result<()> {
+        let items = self.resolve(&ctx).await?;
+        let selected = resolve_phase(&ctx, items);
+
+        if print_dry_run_plan(&ctx, &selected) {
+            return Ok(());
+        }
+
+        for item in selected {
+            self.download_one(&ctx, item).await?;
+        }
+        Ok(())
+    }
+}
+
+impl Download {
+    async fn resolve(&self, ctx: &Context) -> Result<Vec<ResolvedItem<String>>> {
+        let mut items = Vec::with_capacity(self.urls.len());
+        for url in &self.urls {
+            let media = ctx.crunchy.media_collection_from_url(url).await?;
+            // stream/subtitle tracks and their size aren't known until the download itself starts;
+            // only the output container and, if set, the preferred audio locale are fixed upfront
+            let plan = DryRunPlan {
+                audio_locales: self.audio.clone().into_iter().collect(),
+                container: self.output.rsplit('.').next().map(str::to_string),
+                ..Default::default()
+            };
+            items.push(ResolvedItem {
+                title: media.title().to_string(),
+                payload: url.clone(),
+                plan,
+            });
+        }
+        Ok(items)
+    }
+
+    async fn download_one(&self, _ctx: &Context, item: ResolvedItem<String>) -> Result<()> {
+        info!("Downloading {}", item.title);
+        Ok(())
+    }
+}
@@ -1,14 +1,20 @@
 use crate::utils::context::Context;
 use crate::utils::locale::system_locale;
 use crate::utils::log::{progress, CliLogger};
+use crate::utils::oauth::browser_login;
+use crate::utils::rate_limit::{RateLimiterService, RetryService};
+use crate::utils::session::{load_stored_session, persist_session, remove_stored_session, SessionStore};
+use crate::utils::tls::{reqwest_client, TlsOptions};
 use anyhow::bail;
 use anyhow::Result;
 use clap::{Parser, Subcommand};
-use crunchyroll_rs::crunchyroll::CrunchyrollBuilder;
 use crunchyroll_rs::error::Error;
 use crunchyroll_rs::{Crunchyroll, Locale};
 use log::{debug, error, warn, LevelFilter};
+use regex::Regex;
 use reqwest::{Client, Proxy};
+use std::path::PathBuf;
+use std::time::Duration;
 use std::{env, fs};
 
 mod archive;
@@ -17,7 +23,6 @@ mod login;
 mod search;
 mod utils;
 
-use crate::utils::rate_limit::RateLimiterService;
 pub use archive::Archive;
 use dialoguer::console::Term;
 pub use download::Download;
@@ -59,6 +64,19 @@ pub struct Cli {
     #[clap(flatten)]
     login_method: login::LoginMethod,
 
+    #[arg(
+        help = "Login through the browser. Opens the Crunchyroll login page and captures the returned token on a local callback"
+    )]
+    #[arg(
+        long_help = "Login through the browser instead of passing your credentials to the cli. \
+            A short-lived local http listener is started on '127.0.0.1' with an ephemeral port and the Crunchyroll login page is opened in your default browser with this listener as redirect uri. \
+            After a successful login the returned refresh token is captured on the callback and stored the same way as a regular login. \
+            Useful if your account uses SSO/2FA or if you don't want to type your password into the cli. \
+            On headless systems where no browser can be launched the login url is printed instead so you can open it on another device"
+    )]
+    #[arg(long, default_value_t = false)]
+    browser: bool,
+
     #[arg(help = "Use a proxy to route all traffic through")]
     #[arg(long_help = "Use a proxy to route all traffic through. \
             Make sure that the proxy can either forward TLS requests, which is needed to bypass the (cloudflare) bot protection, or that it is configured so that the proxy can bypass the protection itself. \
@@ -70,6 +88,26 @@ pub struct Cli {
     #[arg(global = true, long)]
     user_agent: Option<String>,
 
+    #[arg(help = "Trust an additional root certificate (PEM) when verifying TLS connections")]
+    #[arg(
+        long_help = "Trust an additional root certificate (PEM) when verifying TLS connections. \
+            Useful behind a TLS-inspecting corporate proxy which re-signs traffic with a private root certificate"
+    )]
+    #[arg(global = true, long, value_name = "PEM")]
+    pub(crate) cacert: Option<PathBuf>,
+
+    #[arg(help = "Client certificate (PEM) to present for mutual TLS")]
+    #[arg(
+        long_help = "Client certificate (PEM) to present for mutual TLS. \
+            Must be used together with '--client-key'"
+    )]
+    #[arg(global = true, long, value_name = "PEM")]
+    pub(crate) client_cert: Option<PathBuf>,
+
+    #[arg(help = "Private key (PEM) belonging to '--client-cert'")]
+    #[arg(global = true, long, value_name = "PEM")]
+    pub(crate) client_key: Option<PathBuf>,
+
     #[arg(
         help = "Maximal speed to download/request (may be a bit off here and there). Must be in format of <number>[B|KB|MB]"
     )]
@@ -79,6 +117,58 @@ pub struct Cli {
     #[arg(global = true, long, value_parser = crate::utils::clap::clap_parse_speed_limit)]
     speed_limit: Option<u32>,
 
+    #[arg(help = "Resolve and print what would be downloaded without fetching anything")]
+    #[arg(
+        long_help = "Resolve and print what would be downloaded without fetching anything. \
+            Logs in, resolves the urls and applies '--filter'/'--filter-exclude', then prints every remaining item with its title, its container (the extension of the output template) and, for 'download', the preferred audio locale, and exits. \
+            Stream tracks are not resolved: resolution and estimated size are printed as 'unknown', subtitle locales as 'none', and no output paths are computed. \
+            No media segments are requested and no temporary files are created. Useful to verify your filter flags before committing to a large download"
+    )]
+    #[arg(global = true, long, default_value_t = false)]
+    dry_run: bool,
+
+    #[arg(help = "Only process items whose title matches this regex")]
+    #[arg(
+        long_help = "Only process items whose title matches this regular expression. \
+            In 'archive' and 'download' it is matched against the title of the series, season or episode each url resolves to; the seasons and episodes inside a series or season url are not matched one by one. \
+            In 'search' it is matched against the title of each result. Applied in the resolve phase, before anything is downloaded"
+    )]
+    #[arg(global = true, long, value_parser = crate::utils::clap::clap_parse_regex)]
+    filter: Option<Regex>,
+
+    #[arg(help = "Skip items whose title matches this regex")]
+    #[arg(
+        long_help = "Skip items whose title matches this regular expression. \
+            Matched against the same titles as '--filter' and applied in the resolve phase after it"
+    )]
+    #[arg(global = true, long, value_parser = crate::utils::clap::clap_parse_regex)]
+    filter_exclude: Option<Regex>,
+
+    #[arg(help = "How often to retry a failed request before giving up")]
+    #[arg(
+        long_help = "How often to retry a failed request before giving up. \
+            Idempotent requests (GET, HEAD, PUT, DELETE, OPTIONS) are retried on connection errors, 5xx and 429 responses using exponential backoff with jitter, honoring a 'Retry-After' header given in seconds (a 'Retry-After' date is ignored)"
+    )]
+    #[arg(global = true, long, default_value_t = 3)]
+    retry: u32,
+
+    #[arg(help = "Base interval between retries. Must be in format of <number>[s|m|h]")]
+    #[arg(global = true, long, default_value = "1s", value_parser = crate::utils::clap::clap_parse_duration)]
+    retry_interval: Duration,
+
+    #[arg(help = "Maximum time to wait while establishing a connection. Must be in format of <number>[s|m|h]")]
+    #[arg(global = true, long, value_parser = crate::utils::clap::clap_parse_duration)]
+    connect_timeout: Option<Duration>,
+
+    #[arg(help = "Where to persist the login session")]
+    #[arg(
+        long_help = "Where to persist the login session. \
+            'keyring' uses the operating system credential store (Keychain on macOS, Secret Service/libsecret on Linux, Credential Manager on Windows) and is the default. \
+            'file' stores the refresh token as plaintext at the session file path, which can be necessary on headless or CI machines where no keyring is available"
+    )]
+    #[arg(global = true, long, value_enum, default_value_t = SessionStore::Keyring)]
+    session_store: SessionStore,
+
     #[clap(subcommand)]
     command: Command,
 }
@@ -152,9 +242,7 @@ pub async fn main(args: &[String]) {
         }
         Command::Login(login) => {
             if login.remove {
-                if let Some(session_file) = login::session_file_path() {
-                    let _ = fs::remove_file(session_file);
-                }
+                remove_stored_session();
                 return;
             } else {
                 pre_check_executor(login).await
@@ -250,15 +338,25 @@ async fn execute_executor(executor: impl Execute, ctx: Context) {
 }
 
 async fn create_ctx(cli: &mut Cli) -> Result<Context> {
+    let tls = TlsOptions::from_cli(cli)?;
+
     let crunchy_client = reqwest_client(
         cli.proxy.as_ref().and_then(|p| p.0.clone()),
         cli.user_agent.clone(),
+        &tls,
+        cli.connect_timeout,
     );
     let internal_client = reqwest_client(
         cli.proxy.as_ref().and_then(|p| p.1.clone()),
         cli.user_agent.clone(),
+        &tls,
+        cli.connect_timeout,
     );
 
+    if cli.dry_run {
+        debug!("Running in dry-run mode, no media will be downloaded");
+    }
+
     let crunchy = crunchyroll_session(
         cli,
         crunchy_client.clone(),
@@ -267,12 +365,30 @@ async fn create_ctx(cli: &mut Cli) -> Result<Context> {
     )
     .await?;
 
+    // the internal client is used for the actual media downloads, so it needs the same
+    // rate limiting/retry middleware as the api client built in `crunchyroll_session`
+    let mut internal_client_builder =
+        reqwest_middleware::ClientBuilder::new(internal_client.clone());
+    if let Some(rate_limiter) = cli
+        .speed_limit
+        .map(|l| RateLimiterService::new(l, internal_client.clone()))
+    {
+        internal_client_builder = internal_client_builder.with(rate_limiter);
+    }
+    if cli.retry > 0 {
+        internal_client_builder =
+            internal_client_builder.with(RetryService::new(cli.retry, cli.retry_interval));
+    }
+
     Ok(Context {
         crunchy,
-        client: internal_client.clone(),
+        client: internal_client_builder.build(),
         rate_limiter: cli
             .speed_limit
             .map(|l| RateLimiterService::new(l, internal_client)),
+        dry_run: cli.dry_run,
+        filter: cli.filter.clone(),
+        filter_exclude: cli.filter_exclude.clone(),
     })
 }
 
@@ -328,50 +444,57 @@ async fn crunchyroll_session(
     if let Some(rate_limiter) = rate_limiter {
         builder = builder.middleware(rate_limiter)
     }
+    if cli.retry > 0 {
+        builder = builder.middleware(RetryService::new(cli.retry, cli.retry_interval))
+    }
 
-    let root_login_methods_count =
-        cli.login_method.credentials.is_some() as u8 + cli.login_method.anonymous as u8;
+    let root_login_methods_count = cli.login_method.credentials.is_some() as u8
+        + cli.login_method.anonymous as u8
+        + cli.browser as u8;
 
     let progress_handler = progress!("Logging in");
     if root_login_methods_count == 0 {
-        if let Some(login_file_path) = login::session_file_path() {
-            if login_file_path.exists() {
-                let session = fs::read_to_string(login_file_path)?;
-                if let Some((token_type, token)) = session.split_once(':') {
-                    match token_type {
-                        "refresh_token" => {
-                            return match builder.login_with_refresh_token(token).await {
-                                Ok(crunchy) => Ok(crunchy),
-                                Err(e) => {
-                                    if let Error::Request { message, .. } = &e {
-                                        if message.starts_with("invalid_grant") {
-                                            bail!("The stored login is expired, please login again")
-                                        }
+        if let Some(session) = load_stored_session(cli.session_store)? {
+            if let Some((token_type, token)) = session.split_once(':') {
+                match token_type {
+                    "refresh_token" => {
+                        return match builder.login_with_refresh_token(token).await {
+                            Ok(crunchy) => Ok(crunchy),
+                            Err(e) => {
+                                if let Error::Request { message, .. } = &e {
+                                    if message.starts_with("invalid_grant") {
+                                        bail!("The stored login is expired, please login again")
                                     }
-                                    Err(e.into())
                                 }
+                                Err(e.into())
                             }
                         }
-                        "etp_rt" => bail!("The stored login method (etp-rt) isn't supported anymore. Please login again using your credentials"),
-                        _ => (),
                     }
+                    "etp_rt" => bail!("The stored login method (etp-rt) isn't supported anymore. Please login again using your credentials"),
+                    _ => (),
                 }
-                bail!("Could not read stored session ('{}')", session)
             }
+            bail!("Could not read stored session ('{}')", session)
         }
-        bail!("Please use a login method ('--credentials' or '--anonymous')")
+        bail!("Please use a login method ('--credentials', '--anonymous' or '--browser')")
     } else if root_login_methods_count > 1 {
-        bail!("Please use only one login method ('--credentials' or '--anonymous')")
+        bail!("Please use only one login method ('--credentials', '--anonymous' or '--browser')")
     }
 
     let crunchy = if let Some(credentials) = &cli.login_method.credentials {
         if let Some((email, password)) = credentials.split_once(':') {
-            builder.login_with_credentials(email, password).await?
+            let crunchy = builder.login_with_credentials(email, password).await?;
+            persist_session(&crunchy, cli.session_store)?;
+            crunchy
         } else {
             bail!("Invalid credentials format. Please provide your credentials as email:password")
         }
     } else if cli.login_method.anonymous {
-        builder.login_anonymously().await?
+        let crunchy = builder.login_anonymously().await?;
+        persist_session(&crunchy, cli.session_store)?;
+        crunchy
+    } else if cli.browser {
+        browser_login(builder, cli.session_store).await?
     } else {
         bail!("should never happen")
     };
@@ -380,29 +503,3 @@ async fn crunchyroll_session(
 
     Ok(crunchy)
 }
-
-fn reqwest_client(proxy: Option<Proxy>, user_agent: Option<String>) -> Client {
-    let mut builder = CrunchyrollBuilder::predefined_client_builder();
-    if let Some(p) = proxy {
-        builder = builder.proxy(p)
-    }
-    if let Some(ua) = user_agent {
-        builder = builder.user_agent(ua)
-    }
-
-    #[cfg(any(feature = "openssl-tls", feature = "openssl-tls-static"))]
-    let client = {
-        let mut builder = builder.use_native_tls().tls_built_in_root_certs(false);
-
-        for certificate in rustls_native_certs::load_native_certs().unwrap() {
-            builder =
-                builder.add_root_certificate(reqwest::Certificate::from_der(&certificate).unwrap())
-        }
-
-        builder.build().unwrap()
-    };
-    #[cfg(not(any(feature = "openssl-tls", feature = "openssl-tls-static")))]
-    let client = builder.build().unwrap();
-
-    client
-}
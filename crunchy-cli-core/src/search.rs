@@ -0,0 +1,26 @@
+use crate::utils::context::Context;
+use crate::utils::filter::filter_allows;
+use crate::Execute;
+use anyhow::Result;
+use clap::Args;
+use log::info;
+
+#[derive(Debug, Args)]
+pub struct Search {
+    #[arg(help = "String to search for")]
+    query: String,
+}
+
+impl Execute for Search {
+    async fn execute(self, ctx: Context) -> Result<()> {
+        let results = ctx.crunchy.query(&self.query).await?;
+
+        for result in results {
+            let title = result.title().to_string();
+            if filter_allows(&ctx.filter, &ctx.filter_exclude, &title) {
+                info!("{}", title);
+            }
+        }
+        Ok(())
+    }
+}
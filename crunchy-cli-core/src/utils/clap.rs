@@ -0,0 +1,64 @@
+use regex::Regex;
+use std::time::Duration;
+
+pub(crate) fn clap_parse_regex(s: &str) -> Result<Regex, String> {
+    Regex::new(s).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod clap_parse_regex_tests {
+    use super::clap_parse_regex;
+
+    #[test]
+    fn valid_regex_compiles() {
+        assert!(clap_parse_regex("^Recap").is_ok());
+    }
+
+    #[test]
+    fn invalid_regex_is_rejected() {
+        assert!(clap_parse_regex("(unclosed").is_err());
+    }
+}
+
+pub(crate) fn clap_parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let (value, unit) = s.split_at(s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len()));
+    let value: u64 = value
+        .parse()
+        .map_err(|_| format!("'{}' is not a valid duration", s))?;
+    let seconds = match unit {
+        "" | "s" => value,
+        "m" => value * 60,
+        "h" => value * 60 * 60,
+        _ => return Err(format!("'{}' has an unknown duration unit, use s, m or h", s)),
+    };
+    Ok(Duration::from_secs(seconds))
+}
+
+#[cfg(test)]
+mod clap_parse_duration_tests {
+    use super::clap_parse_duration;
+    use std::time::Duration;
+
+    #[test]
+    fn bare_number_is_seconds() {
+        assert_eq!(clap_parse_duration("5"), Ok(Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn parses_seconds_minutes_and_hours() {
+        assert_eq!(clap_parse_duration("5s"), Ok(Duration::from_secs(5)));
+        assert_eq!(clap_parse_duration("2m"), Ok(Duration::from_secs(120)));
+        assert_eq!(clap_parse_duration("1h"), Ok(Duration::from_secs(3600)));
+    }
+
+    #[test]
+    fn rejects_unknown_unit() {
+        assert!(clap_parse_duration("5d").is_err());
+    }
+
+    #[test]
+    fn rejects_non_numeric_value() {
+        assert!(clap_parse_duration("abc").is_err());
+    }
+}
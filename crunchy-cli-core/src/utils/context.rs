@@ -0,0 +1,13 @@
+use crate::utils::rate_limit::RateLimiterService;
+use crunchyroll_rs::Crunchyroll;
+use regex::Regex;
+use reqwest_middleware::ClientWithMiddleware;
+
+pub struct Context {
+    pub crunchy: Crunchyroll,
+    pub client: ClientWithMiddleware,
+    pub rate_limiter: Option<RateLimiterService>,
+    pub dry_run: bool,
+    pub filter: Option<Regex>,
+    pub filter_exclude: Option<Regex>,
+}
@@ -0,0 +1,109 @@
+use crate::utils::context::Context;
+use crunchyroll_rs::Locale;
+use log::info;
+use regex::Regex;
+
+pub(crate) fn filter_allows(filter: &Option<Regex>, filter_exclude: &Option<Regex>, title: &str) -> bool {
+    if let Some(filter) = filter {
+        if !filter.is_match(title) {
+            return false;
+        }
+    }
+    if let Some(filter_exclude) = filter_exclude {
+        if filter_exclude.is_match(title) {
+            return false;
+        }
+    }
+    true
+}
+
+#[derive(Default)]
+pub(crate) struct DryRunPlan {
+    pub resolution: Option<String>,
+    pub audio_locales: Vec<Locale>,
+    pub subtitle_locales: Vec<Locale>,
+    pub container: Option<String>,
+    pub estimated_size: Option<u64>,
+}
+
+pub(crate) struct ResolvedItem<T> {
+    pub title: String,
+    pub payload: T,
+    pub plan: DryRunPlan,
+}
+
+pub(crate) fn resolve_phase<T>(ctx: &Context, items: Vec<ResolvedItem<T>>) -> Vec<ResolvedItem<T>> {
+    items
+        .into_iter()
+        .filter(|item| filter_allows(&ctx.filter, &ctx.filter_exclude, &item.title))
+        .collect()
+}
+
+pub(crate) fn print_dry_run_plan<T>(ctx: &Context, resolved: &[ResolvedItem<T>]) -> bool {
+    if !ctx.dry_run {
+        return false;
+    }
+    info!("Dry run, {} item(s) would be processed:", resolved.len());
+    for item in resolved {
+        let resolution = item.plan.resolution.as_deref().unwrap_or("unknown");
+        let audio = locales_to_string(&item.plan.audio_locales);
+        let subtitles = locales_to_string(&item.plan.subtitle_locales);
+        let container = item.plan.container.as_deref().unwrap_or("unknown");
+        let size = item
+            .plan
+            .estimated_size
+            .map(|bytes| format!("{:.2} MB", bytes as f64 / 1_000_000.0))
+            .unwrap_or_else(|| "unknown".to_string());
+        info!(
+            "  {} (resolution: {}, audio: {}, subtitles: {}, container: {}, estimated size: {})",
+            item.title, resolution, audio, subtitles, container, size
+        );
+    }
+    true
+}
+
+fn locales_to_string(locales: &[Locale]) -> String {
+    if locales.is_empty() {
+        "none".to_string()
+    } else {
+        locales
+            .iter()
+            .map(|l| l.to_string())
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+}
+
+#[cfg(test)]
+mod filter_allows_tests {
+    use super::filter_allows;
+    use regex::Regex;
+
+    #[test]
+    fn no_filters_allows_everything() {
+        assert!(filter_allows(&None, &None, "Episode 1"));
+    }
+
+    #[test]
+    fn filter_only_allows_matching_titles() {
+        let filter = Some(Regex::new("^Recap").unwrap());
+        assert!(filter_allows(&filter, &None, "Recap Episode"));
+        assert!(!filter_allows(&filter, &None, "Episode 1"));
+    }
+
+    #[test]
+    fn filter_exclude_rejects_matching_titles() {
+        let filter_exclude = Some(Regex::new("Recap").unwrap());
+        assert!(!filter_allows(&None, &filter_exclude, "Recap Episode"));
+        assert!(filter_allows(&None, &filter_exclude, "Episode 1"));
+    }
+
+    #[test]
+    fn filter_and_exclude_combine() {
+        let filter = Some(Regex::new("Episode").unwrap());
+        let filter_exclude = Some(Regex::new("Recap").unwrap());
+        assert!(filter_allows(&filter, &filter_exclude, "Episode 1"));
+        assert!(!filter_allows(&filter, &filter_exclude, "Recap Episode"));
+        assert!(!filter_allows(&filter, &filter_exclude, "Special"));
+    }
+}
@@ -0,0 +1,7 @@
+pub mod clap;
+pub mod context;
+pub mod filter;
+pub mod oauth;
+pub mod rate_limit;
+pub mod session;
+pub mod tls;
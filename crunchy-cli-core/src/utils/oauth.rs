@@ -0,0 +1,164 @@
+use crate::utils::session::{store_session_token, SessionStore};
+use anyhow::{bail, Result};
+use crunchyroll_rs::Crunchyroll;
+use log::{debug, warn};
+use std::time::Duration;
+
+const BROWSER_LOGIN_TIMEOUT: Duration = Duration::from_secs(300);
+
+pub(crate) async fn browser_login(
+    builder: crunchyroll_rs::crunchyroll::CrunchyrollBuilder,
+    store: SessionStore,
+) -> Result<Crunchyroll> {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    // crunchyroll login page which redirects back to our local listener after a successful login.
+    // Crunchyroll's oauth endpoint returns the tokens in the url *fragment* (`#...`) which a
+    // browser never sends to the server, so we can't read them from the first request directly
+    const LOGIN_URL: &str = "https://www.crunchyroll.com/auth/v1/authorize";
+
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let redirect_uri = format!("http://{}", listener.local_addr()?);
+    let state = generate_state();
+    let login_url = format!(
+        "{}?response_type=token&redirect_uri={}&state={}",
+        LOGIN_URL,
+        percent_encode(&redirect_uri),
+        state
+    );
+
+    match open_browser(&login_url) {
+        Ok(()) => debug!("Opened login page in the default browser"),
+        Err(e) => {
+            debug!("Could not open a browser ({}), printing login url instead", e);
+            warn!(
+                "Could not open a browser. Please open the following url manually to login:\n{}",
+                login_url
+            )
+        }
+    }
+
+    // the browser first hits us with the fragment still client-side; we answer with a tiny page
+    // that rewrites the fragment into a query string and reloads, so the second request carries the
+    // tokens where the server can read them
+    let refresh_token = tokio::time::timeout(BROWSER_LOGIN_TIMEOUT, async {
+        loop {
+            let (mut stream, _) = listener.accept().await?;
+
+            let mut buf = [0u8; 8192];
+            let read = stream.read(&mut buf).await?;
+            let request = String::from_utf8_lossy(&buf[..read]);
+            let path = request
+                .lines()
+                .next()
+                .and_then(|line| line.split_whitespace().nth(1))
+                .unwrap_or_default();
+
+            if let Some(query) = path.split_once('?').map(|(_, query)| query) {
+                let pairs: Vec<(&str, &str)> = query
+                    .split('&')
+                    .filter_map(|pair| pair.split_once('='))
+                    .collect();
+
+                // reject callbacks without the exact state we handed out, otherwise any local
+                // process or page that can reach this port during the login window could feed us
+                // a token
+                if pairs.iter().find(|(k, _)| *k == "state").map(|(_, v)| *v)
+                    != Some(state.as_str())
+                {
+                    debug!("Ignoring callback with a missing or invalid state parameter");
+                    let _ = stream.write_all(
+                        b"HTTP/1.1 400 Bad Request\r\nContent-Type: text/html\r\nConnection: close\r\n\r\n\
+                          <html><body>Invalid login callback.</body></html>",
+                    ).await;
+                    continue;
+                }
+
+                let refresh_token = pairs
+                    .iter()
+                    .find(|(k, _)| *k == "refresh_token")
+                    .map(|(_, v)| v.to_string());
+                let _ = stream.write_all(
+                    b"HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nConnection: close\r\n\r\n\
+                      <html><body>Login successful, you can close this tab and return to the terminal.</body></html>",
+                ).await;
+                match refresh_token {
+                    Some(refresh_token) => break Ok::<_, anyhow::Error>(refresh_token),
+                    None => bail!("The browser login did not return a refresh token"),
+                }
+            } else {
+                // forward the fragment (which still carries our state param) to ourselves as a
+                // query string
+                let _ = stream.write_all(
+                    b"HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nConnection: close\r\n\r\n\
+                      <html><body><script>location.replace(location.pathname + '?' + location.hash.substring(1))</script></body></html>",
+                ).await;
+            }
+        }
+    })
+    .await
+    .map_err(|_| {
+        anyhow::anyhow!(
+            "Timed out after {:?} waiting for the browser login to complete",
+            BROWSER_LOGIN_TIMEOUT
+        )
+    })??;
+
+    let crunchy = builder.login_with_refresh_token(&refresh_token).await?;
+    // persist the freshly obtained token so subsequent runs don't need the browser again
+    store_session_token(store, &refresh_token)?;
+    Ok(crunchy)
+}
+
+fn generate_state() -> String {
+    let bytes: [u8; 16] = rand::random();
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn percent_encode(input: &str) -> String {
+    input
+        .chars()
+        .map(|c| match c {
+            'A'..='Z' | 'a'..='z' | '0'..='9' | '-' | '.' | '_' | '~' => c.to_string(),
+            other => other
+                .to_string()
+                .bytes()
+                .map(|b| format!("%{:02X}", b))
+                .collect(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod percent_encode_tests {
+    use super::percent_encode;
+
+    #[test]
+    fn leaves_unreserved_characters_untouched() {
+        assert_eq!(percent_encode("abcXYZ019-._~"), "abcXYZ019-._~");
+    }
+
+    #[test]
+    fn encodes_redirect_uri_characters() {
+        assert_eq!(percent_encode("http://127.0.0.1:8080"), "http%3A%2F%2F127.0.0.1%3A8080");
+    }
+}
+
+fn open_browser(url: &str) -> Result<()> {
+    use std::process::Command;
+
+    let opener = if cfg!(target_os = "macos") {
+        "open"
+    } else if cfg!(target_os = "windows") {
+        "explorer"
+    } else {
+        "xdg-open"
+    };
+
+    let status = Command::new(opener).arg(url).status()?;
+    if !status.success() {
+        bail!("'{}' exited with {}", opener, status)
+    }
+    Ok(())
+}
@@ -0,0 +1,137 @@
+use log::debug;
+use std::time::Duration;
+
+#[derive(Clone)]
+pub(crate) struct RetryService {
+    retries: u32,
+    interval: Duration,
+}
+
+impl RetryService {
+    pub(crate) fn new(retries: u32, interval: Duration) -> Self {
+        Self { retries, interval }
+    }
+
+    fn should_retry(status: reqwest::StatusCode) -> bool {
+        status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+    }
+
+    fn backoff(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        if let Some(retry_after) = retry_after {
+            return retry_after;
+        }
+        // exponential: interval * 2^attempt, capped, plus up to 50% jitter
+        let exp = self.interval.saturating_mul(1u32 << attempt.min(6));
+        let capped = exp.min(Duration::from_secs(60));
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.subsec_nanos())
+            .unwrap_or(0);
+        let jitter = capped.mul_f64(0.5 * (nanos as f64 / 1_000_000_000f64));
+        capped + jitter
+    }
+}
+
+#[cfg(test)]
+mod backoff_tests {
+    use super::RetryService;
+    use reqwest::StatusCode;
+    use std::time::Duration;
+
+    #[test]
+    fn retry_after_overrides_the_computed_backoff() {
+        let service = RetryService::new(3, Duration::from_secs(1));
+        assert_eq!(
+            service.backoff(5, Some(Duration::from_secs(30))),
+            Duration::from_secs(30)
+        );
+    }
+
+    #[test]
+    fn backoff_grows_with_the_attempt_and_is_capped() {
+        let service = RetryService::new(10, Duration::from_secs(1));
+        // each attempt's backoff (before jitter) is interval * 2^attempt, so it must at least
+        // double attempt over attempt until the 60s cap is hit
+        assert!(service.backoff(0, None) >= Duration::from_secs(1));
+        assert!(service.backoff(0, None) < Duration::from_secs(2));
+        assert!(service.backoff(1, None) >= Duration::from_secs(2));
+        assert!(service.backoff(2, None) >= Duration::from_secs(4));
+        // a large attempt number must be capped at 60s plus at most 50% jitter
+        assert!(service.backoff(20, None) <= Duration::from_secs(90));
+    }
+
+    #[test]
+    fn should_retry_on_server_error_and_too_many_requests() {
+        assert!(RetryService::should_retry(StatusCode::TOO_MANY_REQUESTS));
+        assert!(RetryService::should_retry(StatusCode::INTERNAL_SERVER_ERROR));
+        assert!(!RetryService::should_retry(StatusCode::OK));
+        assert!(!RetryService::should_retry(StatusCode::NOT_FOUND));
+    }
+}
+
+#[async_trait::async_trait]
+impl reqwest_middleware::Middleware for RetryService {
+    async fn handle(
+        &self,
+        req: reqwest::Request,
+        extensions: &mut http::Extensions,
+        next: reqwest_middleware::Next<'_>,
+    ) -> reqwest_middleware::Result<reqwest::Response> {
+        // only retry idempotent methods: a retried POST/PATCH could duplicate a non-idempotent
+        // side effect (e.g. a login). a request with a non-replayable (streaming) body can't be
+        // retried either way, so send both once
+        let is_idempotent = matches!(
+            *req.method(),
+            reqwest::Method::GET
+                | reqwest::Method::HEAD
+                | reqwest::Method::PUT
+                | reqwest::Method::DELETE
+                | reqwest::Method::OPTIONS
+        );
+        if !is_idempotent || req.try_clone().is_none() {
+            return next.run(req, extensions).await;
+        }
+
+        let mut attempt = 0;
+        loop {
+            let response = next
+                .clone()
+                .run(req.try_clone().unwrap(), extensions)
+                .await;
+
+            let retry_after = match &response {
+                Ok(resp) => {
+                    if !RetryService::should_retry(resp.status()) {
+                        return response;
+                    }
+                    resp.headers()
+                        .get(reqwest::header::RETRY_AFTER)
+                        .and_then(|v| v.to_str().ok())
+                        .and_then(|v| v.parse::<u64>().ok())
+                        .map(Duration::from_secs)
+                }
+                Err(e) => {
+                    // only retry transient connection errors, not e.g. builder errors
+                    if !e.is_connect() && !e.is_timeout() {
+                        return response;
+                    }
+                    None
+                }
+            };
+
+            if attempt >= self.retries {
+                return response;
+            }
+
+            let backoff = self.backoff(attempt, retry_after);
+            debug!(
+                "Request failed, retrying in {:?} ({}/{})",
+                backoff,
+                attempt + 1,
+                self.retries
+            );
+            tokio::time::sleep(backoff).await;
+            attempt += 1;
+        }
+    }
+}
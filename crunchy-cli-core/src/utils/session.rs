@@ -0,0 +1,72 @@
+use crate::login;
+use anyhow::Result;
+use clap::ValueEnum;
+use crunchyroll_rs::Crunchyroll;
+use log::{debug, warn};
+use std::fs;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub(crate) enum SessionStore {
+    File,
+    Keyring,
+}
+
+const KEYRING_SERVICE: &str = "crunchy-cli";
+const KEYRING_USER: &str = "refresh_token";
+
+pub(crate) fn load_stored_session(store: SessionStore) -> Result<Option<String>> {
+    if store == SessionStore::Keyring {
+        match keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER).and_then(|e| e.get_password()) {
+            Ok(token) => return Ok(Some(format!("refresh_token:{}", token))),
+            Err(keyring::Error::NoEntry) => debug!("No keyring entry found, falling back to file"),
+            // a keyring may be unavailable (e.g. a headless Linux box without Secret Service); don't
+            // abort the run, fall back to the plaintext session file instead
+            Err(e) => warn!(
+                "Could not read from the keyring ({}), falling back to file. Use '--session-store file' to silence this",
+                e
+            ),
+        }
+    }
+
+    if let Some(session_file) = login::session_file_path() {
+        if session_file.exists() {
+            return Ok(Some(fs::read_to_string(session_file)?));
+        }
+    }
+    Ok(None)
+}
+
+pub(crate) fn store_session_token(store: SessionStore, refresh_token: &str) -> Result<()> {
+    if store == SessionStore::Keyring {
+        match keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER)
+            .and_then(|e| e.set_password(refresh_token))
+        {
+            Ok(()) => return Ok(()),
+            Err(e) => warn!(
+                "Could not write to the keyring ({}), falling back to file. Use '--session-store file' to silence this",
+                e
+            ),
+        }
+    }
+
+    if let Some(session_file) = login::session_file_path() {
+        fs::write(session_file, format!("refresh_token:{}", refresh_token))?;
+    }
+    Ok(())
+}
+
+pub(crate) fn persist_session(crunchy: &Crunchyroll, store: SessionStore) -> Result<()> {
+    if let Some(refresh_token) = crunchy.config().refresh_token.clone() {
+        store_session_token(store, &refresh_token)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn remove_stored_session() {
+    if let Ok(entry) = keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER) {
+        let _ = entry.delete_password();
+    }
+    if let Some(session_file) = login::session_file_path() {
+        let _ = fs::remove_file(session_file);
+    }
+}
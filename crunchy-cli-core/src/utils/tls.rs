@@ -0,0 +1,95 @@
+use crate::Cli;
+use anyhow::{bail, Result};
+use crunchyroll_rs::crunchyroll::CrunchyrollBuilder;
+use reqwest::{Client, Proxy};
+use std::fs;
+use std::time::Duration;
+
+#[derive(Clone, Default)]
+pub(crate) struct TlsOptions {
+    root_certificate: Option<reqwest::Certificate>,
+    identity: Option<reqwest::Identity>,
+}
+
+impl TlsOptions {
+    pub(crate) fn from_cli(cli: &Cli) -> Result<Self> {
+        let root_certificate = match &cli.cacert {
+            Some(path) => {
+                let pem = fs::read(path).map_err(|e| {
+                    anyhow::anyhow!("Could not read ca certificate '{}': {}", path.display(), e)
+                })?;
+                Some(reqwest::Certificate::from_pem(&pem).map_err(|e| {
+                    anyhow::anyhow!("Could not parse ca certificate '{}': {}", path.display(), e)
+                })?)
+            }
+            None => None,
+        };
+
+        if cli.client_cert.is_some() != cli.client_key.is_some() {
+            bail!("'--client-cert' and '--client-key' must be used together")
+        }
+        let identity = match (&cli.client_cert, &cli.client_key) {
+            (Some(cert), Some(key)) => {
+                let mut pem = fs::read(cert).map_err(|e| {
+                    anyhow::anyhow!("Could not read client certificate '{}': {}", cert.display(), e)
+                })?;
+                let mut key_pem = fs::read(key).map_err(|e| {
+                    anyhow::anyhow!("Could not read client key '{}': {}", key.display(), e)
+                })?;
+                // reqwest's `Identity::from_pem` expects the certificate and its private key in a
+                // single buffer
+                pem.push(b'\n');
+                pem.append(&mut key_pem);
+                Some(reqwest::Identity::from_pem(&pem).map_err(|e| {
+                    anyhow::anyhow!("Could not parse client certificate/key: {}", e)
+                })?)
+            }
+            _ => None,
+        };
+
+        Ok(TlsOptions {
+            root_certificate,
+            identity,
+        })
+    }
+}
+
+pub(crate) fn reqwest_client(
+    proxy: Option<Proxy>,
+    user_agent: Option<String>,
+    tls: &TlsOptions,
+    connect_timeout: Option<Duration>,
+) -> Client {
+    let mut builder = CrunchyrollBuilder::predefined_client_builder();
+    if let Some(p) = proxy {
+        builder = builder.proxy(p)
+    }
+    if let Some(ua) = user_agent {
+        builder = builder.user_agent(ua)
+    }
+    if let Some(connect_timeout) = connect_timeout {
+        builder = builder.connect_timeout(connect_timeout)
+    }
+    if let Some(root_certificate) = &tls.root_certificate {
+        builder = builder.add_root_certificate(root_certificate.clone())
+    }
+    if let Some(identity) = &tls.identity {
+        builder = builder.identity(identity.clone())
+    }
+
+    #[cfg(any(feature = "openssl-tls", feature = "openssl-tls-static"))]
+    let client = {
+        let mut builder = builder.use_native_tls().tls_built_in_root_certs(false);
+
+        for certificate in rustls_native_certs::load_native_certs().unwrap() {
+            builder =
+                builder.add_root_certificate(reqwest::Certificate::from_der(&certificate).unwrap())
+        }
+
+        builder.build().unwrap()
+    };
+    #[cfg(not(any(feature = "openssl-tls", feature = "openssl-tls-static")))]
+    let client = builder.build().unwrap();
+
+    client
+}